@@ -1,11 +1,13 @@
+use std::ffi::c_void;
 use std::mem::size_of;
+use std::path::Path;
 use std::process::Command;
 use std::ptr::null_mut;
 use std::thread::sleep;
 use std::time::Duration;
 use anyhow::anyhow;
-use windows::Win32::Foundation::RECT;
-use windows::Win32::Graphics::Gdi::{BI_BITFIELDS, BitBlt, BITMAPINFO, BITMAPINFOHEADER, CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, DIB_RGB_COLORS, GetDC, GetDIBits, ReleaseDC, SelectObject, SetDIBits, SRCCOPY};
+use windows::Win32::Foundation::{HWND, RECT};
+use windows::Win32::Graphics::Gdi::{AC_SRC_OVER, AlphaBlend, BITMAPINFO, BITMAPINFOHEADER, BitBlt, BLENDFUNCTION, CreateBitmap, CreateCompatibleDC, CreateDIBSection, DeleteDC, DeleteObject, DIB_RGB_COLORS, GetDC, HBITMAP, HDC, HGDIOBJ, MaskBlt, ReleaseDC, SelectObject, SRCCOPY};
 use windows::Win32::UI::HiDpi::{PROCESS_PER_MONITOR_DPI_AWARE, SetProcessDpiAwareness};
 use windows::Win32::UI::WindowsAndMessaging::{FindWindowW, GetClientRect, SetWindowPos, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOZORDER};
 
@@ -13,6 +15,9 @@ pub struct Image {
     pub width: usize,
     pub height: usize,
     pub buf: Vec<u8>,
+    // Grayscale cutoff consulted by `is_white`; defaults to the old fixed value, replaced by
+    // `apply_otsu_threshold` once a caller wants it adapted to the frame's own histogram.
+    pub threshold: u8,
 }
 
 impl Image {
@@ -21,74 +26,7 @@ impl Image {
             width,
             height,
             buf: vec![0u8; width * height * 4],
-        }
-    }
-    pub fn from_window(class_name: &str, window_name: &str, x: i32, y: i32) -> anyhow::Result<Self> {
-        unsafe {
-            let hwnd = FindWindowW(class_name, window_name);
-            if hwnd.0 == 0 {
-                return Err(anyhow!("Window not found"));
-            }
-            let mut rect = RECT::default();
-            let _ = GetClientRect(hwnd, &mut rect);
-            let width = (rect.right - rect.left) as usize;
-            let height = (rect.bottom - rect.top) as usize;
-            if width == 0 || height == 0 {
-                return Err(anyhow!("Window not shown"));
-            }
-            let hdc = GetDC(hwnd);
-            let hmemdc = CreateCompatibleDC(hdc);
-            let hbm = CreateCompatibleBitmap(hdc, width as i32, height as i32);
-            let hbm_old = SelectObject(hmemdc, hbm);
-            BitBlt(hmemdc, 0, 0, width as i32, height as i32, hdc, x, y, SRCCOPY);
-            let mut bmi_buf = [0u8; (size_of::<BITMAPINFO>() + 8)]; // 因为调色板 bmiColors 是个变长数组，RGB 三个颜色，数组实际长度是 3，比 1 个元素多出 8 字节
-            let bmi = &mut *(bmi_buf.as_mut_ptr() as *mut BITMAPINFO);
-            bmi.bmiHeader.biSize = size_of::<BITMAPINFOHEADER>() as u32;
-            GetDIBits(hmemdc, hbm, 0, 0, null_mut(), bmi, DIB_RGB_COLORS);
-            bmi.bmiHeader.biBitCount = 32;
-            bmi.bmiHeader.biCompression = BI_BITFIELDS as u32;
-            bmi.bmiColors.get_unchecked_mut(0).rgbRed = 255;
-            bmi.bmiColors.get_unchecked_mut(1).rgbGreen = 255;
-            bmi.bmiColors.get_unchecked_mut(2).rgbBlue = 255;
-            let mut buf = vec![0u8; width * height * 4];
-            GetDIBits(hmemdc, hbm, 0, height as u32, buf.as_mut_ptr() as _, bmi, DIB_RGB_COLORS);
-            let _ = SelectObject(hmemdc, hbm_old);
-            DeleteObject(hbm);
-            DeleteDC(hmemdc);
-            ReleaseDC(hwnd, hdc);
-            Ok(Self {
-                width,
-                height,
-                buf,
-            })
-        }
-    }
-    pub fn paint_to_window(&self, class_name: &str, window_name: &str, x: i32, y: i32) -> anyhow::Result<()> {
-        unsafe {
-            let hwnd = FindWindowW(class_name, window_name);
-            if hwnd.0 == 0 {
-                return Err(anyhow!("Window not found"));
-            }
-            let hdc = GetDC(hwnd);
-            let hmemdc = CreateCompatibleDC(hdc);
-            let hbm = CreateCompatibleBitmap(hdc, self.width as i32, self.height as i32);
-            let hbm_old = SelectObject(hmemdc, hbm);
-            let mut bmi_buf = [0u8; (size_of::<BITMAPINFO>() + 8)];
-            let bmi = &mut *(bmi_buf.as_mut_ptr() as *mut BITMAPINFO);
-            bmi.bmiHeader.biSize = size_of::<BITMAPINFOHEADER>() as u32;
-            GetDIBits(hmemdc, hbm, 0, 0, null_mut(), bmi, DIB_RGB_COLORS);
-            bmi.bmiHeader.biBitCount = 32;
-            bmi.bmiHeader.biCompression = BI_BITFIELDS as u32;
-            bmi.bmiColors.get_unchecked_mut(0).rgbRed = 255;
-            bmi.bmiColors.get_unchecked_mut(1).rgbGreen = 255;
-            bmi.bmiColors.get_unchecked_mut(2).rgbBlue = 255;
-            SetDIBits(hmemdc, hbm, 0, self.height as u32, self.buf.as_ptr() as _, bmi, DIB_RGB_COLORS);
-            BitBlt(hdc, x, y, self.width as i32, self.height as i32, hmemdc, 0, 0, SRCCOPY);
-            let _ = SelectObject(hmemdc, hbm_old);
-            DeleteObject(hbm);
-            DeleteDC(hmemdc);
-            ReleaseDC(hwnd, hdc);
-            Ok(())
+            threshold: 192,
         }
     }
     pub fn from_fn<F: Fn(usize, usize) -> (u8, u8, u8)>(width: usize, height: usize, f: F) -> Self {
@@ -101,6 +39,74 @@ impl Image {
         }
         image
     }
+    pub fn from_bmp_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let data = std::fs::read(path)?;
+        if data.len() < 14 + 40 || &data[0..2] != b"BM" {
+            return Err(anyhow!("Not a BMP file"));
+        }
+        let pixel_offset = u32::from_le_bytes(data[10..14].try_into()?) as usize;
+        let header_size = u32::from_le_bytes(data[14..18].try_into()?);
+        if header_size < 40 {
+            return Err(anyhow!("Unsupported BMP info header"));
+        }
+        let width = i32::from_le_bytes(data[18..22].try_into()?) as usize;
+        let raw_height = i32::from_le_bytes(data[22..26].try_into()?);
+        let height = raw_height.unsigned_abs() as usize;
+        let bit_count = u16::from_le_bytes(data[28..30].try_into()?);
+        let compression = u32::from_le_bytes(data[30..34].try_into()?);
+        if compression != 0 {
+            return Err(anyhow!("Only uncompressed BI_RGB BMP files are supported"));
+        }
+        if bit_count != 24 && bit_count != 32 {
+            return Err(anyhow!("Only 24/32-bit BMP files are supported"));
+        }
+        let bytes_per_pixel = (bit_count / 8) as usize;
+        let row_stride = (width * bytes_per_pixel + 3) & !3;
+        if data.len() < pixel_offset + height * row_stride {
+            return Err(anyhow!("BMP pixel data is shorter than the header declares"));
+        }
+        let top_down = raw_height < 0;
+        let mut image = Self::new(width, height);
+        for row in 0..height {
+            let src_offset = pixel_offset + row * row_stride;
+            let src_row = &data[src_offset..src_offset + width * bytes_per_pixel];
+            // `buf` is bottom-up (see `get_offset`), so a top-down BMP needs its rows reversed on the way in.
+            let dst_row = if top_down { height - 1 - row } else { row };
+            let dst_offset = dst_row * width * 4;
+            for x in 0..width {
+                let s = x * bytes_per_pixel;
+                image.buf[dst_offset + x * 4] = src_row[s];
+                image.buf[dst_offset + x * 4 + 1] = src_row[s + 1];
+                image.buf[dst_offset + x * 4 + 2] = src_row[s + 2];
+            }
+        }
+        Ok(image)
+    }
+    pub fn to_bmp_file<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let header_size = 40u32;
+        let pixel_offset = 14 + header_size;
+        let file_size = pixel_offset as usize + self.buf.len();
+        let mut data = Vec::with_capacity(file_size);
+        data.extend_from_slice(b"BM");
+        data.extend_from_slice(&(file_size as u32).to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes()); // bfReserved1
+        data.extend_from_slice(&0u16.to_le_bytes()); // bfReserved2
+        data.extend_from_slice(&pixel_offset.to_le_bytes());
+        data.extend_from_slice(&header_size.to_le_bytes());
+        data.extend_from_slice(&(self.width as i32).to_le_bytes());
+        data.extend_from_slice(&(self.height as i32).to_le_bytes()); // positive biHeight: bottom-up, matching `buf`
+        data.extend_from_slice(&1u16.to_le_bytes()); // biPlanes
+        data.extend_from_slice(&32u16.to_le_bytes()); // biBitCount
+        data.extend_from_slice(&0u32.to_le_bytes()); // biCompression = BI_RGB
+        data.extend_from_slice(&(self.buf.len() as u32).to_le_bytes()); // biSizeImage
+        data.extend_from_slice(&0i32.to_le_bytes()); // biXPelsPerMeter
+        data.extend_from_slice(&0i32.to_le_bytes()); // biYPelsPerMeter
+        data.extend_from_slice(&0u32.to_le_bytes()); // biClrUsed
+        data.extend_from_slice(&0u32.to_le_bytes()); // biClrImportant
+        data.extend_from_slice(&self.buf);
+        std::fs::write(path, data)?;
+        Ok(())
+    }
     pub fn get_offset(&self, x: usize, y: usize) -> usize {
         ((self.height - 1 - y) * self.width + x) * 4
     }
@@ -119,7 +125,47 @@ impl Image {
         return r / 4 + g / 2 + b / 4;
     }
     pub fn is_white(&self, x: usize, y: usize) -> bool {
-        self.get_grayscale_color(x, y) > 192
+        self.get_grayscale_color(x, y) > self.threshold
+    }
+    /// Otsu's method: the threshold maximizing between-class variance of the grayscale histogram,
+    /// computed in O(256) by accumulating running sums while sweeping the candidate threshold.
+    pub fn compute_otsu_threshold(&self) -> u8 {
+        let mut histogram = [0u32; 256];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                histogram[self.get_grayscale_color(x, y) as usize] += 1;
+            }
+        }
+        let total = (self.width * self.height) as f64;
+        let sum_all: f64 = histogram.iter().enumerate().map(|(i, &count)| i as f64 * count as f64).sum();
+        let mut weight_below = 0f64;
+        let mut sum_below = 0f64;
+        let mut best_threshold = 0u8;
+        let mut best_variance = 0f64;
+        for (t, &count) in histogram.iter().enumerate() {
+            weight_below += count as f64;
+            if weight_below == 0.0 {
+                continue;
+            }
+            let weight_above = total - weight_below;
+            if weight_above <= 0.0 {
+                break;
+            }
+            sum_below += t as f64 * count as f64;
+            let mean_below = sum_below / weight_below;
+            let mean_above = (sum_all - sum_below) / weight_above;
+            let omega0 = weight_below / total;
+            let omega1 = weight_above / total;
+            let variance = omega0 * omega1 * (mean_below - mean_above) * (mean_below - mean_above);
+            if variance > best_variance {
+                best_variance = variance;
+                best_threshold = t as u8;
+            }
+        }
+        best_threshold
+    }
+    pub fn apply_otsu_threshold(&mut self) {
+        self.threshold = self.compute_otsu_threshold();
     }
     pub fn is_edge(&self, x: usize, y: usize) -> bool {
         let a = self.is_white(x, y);
@@ -141,8 +187,369 @@ impl Image {
             }
         })
     }
+    pub fn to_taskmgr_style_dithered(&self) -> Self {
+        // Floyd-Steinberg error diffusion over grayscale, dispersing the quantization error to the
+        // unvisited neighbours so motion/gradients survive the two-tone palette instead of banding.
+        let mut gray: Vec<f32> = (0..self.width * self.height)
+            .map(|i| self.get_grayscale_color(i % self.width, i / self.width) as f32)
+            .collect();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let idx = y * self.width + x;
+                let old = gray[idx];
+                let chosen = if old > 127.0 { 255.0 } else { 0.0 };
+                gray[idx] = chosen;
+                let err = old - chosen;
+                if x + 1 < self.width {
+                    gray[idx + 1] += err * 7.0 / 16.0;
+                }
+                if y + 1 < self.height {
+                    if x > 0 {
+                        gray[idx + self.width - 1] += err * 3.0 / 16.0;
+                    }
+                    gray[idx + self.width] += err * 5.0 / 16.0;
+                    if x + 1 < self.width {
+                        gray[idx + self.width + 1] += err * 1.0 / 16.0;
+                    }
+                }
+            }
+        }
+        Self::from_fn(self.width - 1, self.height - 1, |x, y| {
+            if x == 0 || y == 0 || x == self.width - 2 || y == self.height - 2 || self.is_edge(x, y) {
+                (0x4c, 0x9d, 0xcb) // 边框
+            } else if x % 50 == 0 || y % 50 == 0 {
+                (0xd9, 0xea, 0xf4) // 网格
+            } else if gray[y * self.width + x] > 127.0 {
+                (0xff, 0xff, 0xff) // 白色
+            } else {
+                (0xf1, 0xf6, 0xfa) // 黑色
+            }
+        })
+    }
+    // Mitchell-Netravali (B = C = 1/3) cubic filter, used by `resize` for both axes of the separable convolution.
+    fn mitchell_netravali(t: f32) -> f32 {
+        let (b, c) = (1.0 / 3.0, 1.0 / 3.0);
+        let t = t.abs();
+        if t < 1.0 {
+            ((12.0 - 9.0 * b - 6.0 * c) * t * t * t + (-18.0 + 12.0 * b + 6.0 * c) * t * t + (6.0 - 2.0 * b)) / 6.0
+        } else if t < 2.0 {
+            ((-b - 6.0 * c) * t * t * t + (6.0 * b + 30.0 * c) * t * t + (-12.0 * b - 48.0 * c) * t + (8.0 * b + 24.0 * c)) / 6.0
+        } else {
+            0.0
+        }
+    }
+    pub fn resize(&self, new_width: usize, new_height: usize) -> Self {
+        if self.width == 0 || self.height == 0 {
+            // Nothing to sample from; clamp() below would panic with min > max, so hand back a blank target.
+            return Self::new(new_width, new_height);
+        }
+        // Horizontal pass first, then vertical, so each pass is a 1-D resample over already-axis-aligned samples.
+        let horizontal = Self::from_fn(new_width, self.height, |x, y| {
+            let src_x = (x as f32 + 0.5) * self.width as f32 / new_width as f32 - 0.5;
+            let base = src_x.floor() as isize;
+            let mut sum = [0f32; 3];
+            let mut weight_sum = 0f32;
+            for i in base - 1..=base + 2 {
+                let weight = Self::mitchell_netravali(src_x - i as f32);
+                if weight == 0.0 {
+                    continue;
+                }
+                let sx = i.clamp(0, self.width as isize - 1) as usize;
+                let (r, g, b) = self.get_color(sx, y);
+                sum[0] += r as f32 * weight;
+                sum[1] += g as f32 * weight;
+                sum[2] += b as f32 * weight;
+                weight_sum += weight;
+            }
+            (
+                (sum[0] / weight_sum).round().clamp(0.0, 255.0) as u8,
+                (sum[1] / weight_sum).round().clamp(0.0, 255.0) as u8,
+                (sum[2] / weight_sum).round().clamp(0.0, 255.0) as u8,
+            )
+        });
+        Self::from_fn(new_width, new_height, |x, y| {
+            let src_y = (y as f32 + 0.5) * horizontal.height as f32 / new_height as f32 - 0.5;
+            let base = src_y.floor() as isize;
+            let mut sum = [0f32; 3];
+            let mut weight_sum = 0f32;
+            for i in base - 1..=base + 2 {
+                let weight = Self::mitchell_netravali(src_y - i as f32);
+                if weight == 0.0 {
+                    continue;
+                }
+                let sy = i.clamp(0, horizontal.height as isize - 1) as usize;
+                let (r, g, b) = horizontal.get_color(x, sy);
+                sum[0] += r as f32 * weight;
+                sum[1] += g as f32 * weight;
+                sum[2] += b as f32 * weight;
+                weight_sum += weight;
+            }
+            (
+                (sum[0] / weight_sum).round().clamp(0.0, 255.0) as u8,
+                (sum[1] / weight_sum).round().clamp(0.0, 255.0) as u8,
+                (sum[2] / weight_sum).round().clamp(0.0, 255.0) as u8,
+            )
+        })
+    }
+}
+
+/// Caches the resolved `HWND` plus a `CreateDIBSection`-backed memory DC across frames, so repeated
+/// captures cost a window lookup, a `BitBlt` and a memory copy instead of rebuilding the whole GDI chain each time.
+pub struct WindowCapture {
+    class_name: String,
+    window_name: String,
+    hwnd: HWND,
+    hdc: HDC,
+    hmemdc: HDC,
+    hbm: HBITMAP,
+    hbm_old: HGDIOBJ,
+    bits: *mut u8,
+    width: usize,
+    height: usize,
 }
 
+impl WindowCapture {
+    pub fn new(class_name: &str, window_name: &str) -> Self {
+        Self {
+            class_name: class_name.to_string(),
+            window_name: window_name.to_string(),
+            hwnd: HWND(0),
+            hdc: HDC(0),
+            hmemdc: HDC(0),
+            hbm: HBITMAP(0),
+            hbm_old: HGDIOBJ(0),
+            bits: null_mut(),
+            width: 0,
+            height: 0,
+        }
+    }
+    unsafe fn teardown(&mut self) {
+        if self.hbm.0 != 0 {
+            SelectObject(self.hmemdc, self.hbm_old);
+            DeleteObject(self.hbm);
+        }
+        if self.hmemdc.0 != 0 {
+            DeleteDC(self.hmemdc);
+        }
+        if self.hdc.0 != 0 {
+            ReleaseDC(self.hwnd, self.hdc);
+        }
+        self.hwnd = HWND(0);
+        self.hdc = HDC(0);
+        self.hmemdc = HDC(0);
+        self.hbm = HBITMAP(0);
+        self.hbm_old = HGDIOBJ(0);
+        self.bits = null_mut();
+        self.width = 0;
+        self.height = 0;
+    }
+    pub fn capture(&mut self, x: i32, y: i32) -> anyhow::Result<Image> {
+        unsafe {
+            let hwnd = FindWindowW(self.class_name.as_str(), self.window_name.as_str());
+            if hwnd.0 == 0 {
+                self.teardown();
+                return Err(anyhow!("Window not found"));
+            }
+            let mut rect = RECT::default();
+            let _ = GetClientRect(hwnd, &mut rect);
+            let width = (rect.right - rect.left) as usize;
+            let height = (rect.bottom - rect.top) as usize;
+            if width == 0 || height == 0 {
+                return Err(anyhow!("Window not shown"));
+            }
+            if hwnd != self.hwnd || width != self.width || height != self.height {
+                self.teardown();
+                let hdc = GetDC(hwnd);
+                let hmemdc = CreateCompatibleDC(hdc);
+                let mut bmi = BITMAPINFO::default();
+                bmi.bmiHeader.biSize = size_of::<BITMAPINFOHEADER>() as u32;
+                bmi.bmiHeader.biWidth = width as i32;
+                bmi.bmiHeader.biHeight = height as i32; // positive: bottom-up, matching `Image::buf`
+                bmi.bmiHeader.biPlanes = 1;
+                bmi.bmiHeader.biBitCount = 32;
+                bmi.bmiHeader.biCompression = 0; // BI_RGB
+                let mut bits: *mut c_void = null_mut();
+                let hbm = CreateDIBSection(hdc, &bmi, DIB_RGB_COLORS, &mut bits, None, 0)?;
+                let hbm_old = SelectObject(hmemdc, hbm);
+                self.hwnd = hwnd;
+                self.hdc = hdc;
+                self.hmemdc = hmemdc;
+                self.hbm = hbm;
+                self.hbm_old = hbm_old;
+                self.bits = bits as *mut u8;
+                self.width = width;
+                self.height = height;
+            }
+            BitBlt(self.hmemdc, 0, 0, self.width as i32, self.height as i32, self.hdc, x, y, SRCCOPY);
+            let mut image = Image::new(self.width, self.height);
+            std::ptr::copy_nonoverlapping(self.bits, image.buf.as_mut_ptr(), image.buf.len());
+            Ok(image)
+        }
+    }
+}
+
+impl Drop for WindowCapture {
+    fn drop(&mut self) {
+        unsafe { self.teardown() }
+    }
+}
+
+/// The paint-side counterpart of `WindowCapture`: keeps a `CreateDIBSection`-backed memory DC alive
+/// across frames so painting becomes a direct memory write followed by a single `BitBlt`.
+pub struct WindowPainter {
+    class_name: String,
+    window_name: String,
+    hwnd: HWND,
+    hdc: HDC,
+    hmemdc: HDC,
+    hbm: HBITMAP,
+    hbm_old: HGDIOBJ,
+    bits: *mut u8,
+    width: usize,
+    height: usize,
+}
+
+impl WindowPainter {
+    pub fn new(class_name: &str, window_name: &str) -> Self {
+        Self {
+            class_name: class_name.to_string(),
+            window_name: window_name.to_string(),
+            hwnd: HWND(0),
+            hdc: HDC(0),
+            hmemdc: HDC(0),
+            hbm: HBITMAP(0),
+            hbm_old: HGDIOBJ(0),
+            bits: null_mut(),
+            width: 0,
+            height: 0,
+        }
+    }
+    unsafe fn teardown(&mut self) {
+        if self.hbm.0 != 0 {
+            SelectObject(self.hmemdc, self.hbm_old);
+            DeleteObject(self.hbm);
+        }
+        if self.hmemdc.0 != 0 {
+            DeleteDC(self.hmemdc);
+        }
+        if self.hdc.0 != 0 {
+            ReleaseDC(self.hwnd, self.hdc);
+        }
+        self.hwnd = HWND(0);
+        self.hdc = HDC(0);
+        self.hmemdc = HDC(0);
+        self.hbm = HBITMAP(0);
+        self.hbm_old = HGDIOBJ(0);
+        self.bits = null_mut();
+        self.width = 0;
+        self.height = 0;
+    }
+    /// Resolves `hwnd` and (re)allocates the `CreateDIBSection` surface when the target window or the
+    /// requested size changed; a no-op otherwise. Shared by every `paint*` method below.
+    unsafe fn ensure_surface(&mut self, width: usize, height: usize) -> anyhow::Result<()> {
+        let hwnd = FindWindowW(self.class_name.as_str(), self.window_name.as_str());
+        if hwnd.0 == 0 {
+            self.teardown();
+            return Err(anyhow!("Window not found"));
+        }
+        if hwnd != self.hwnd || width != self.width || height != self.height {
+            self.teardown();
+            let hdc = GetDC(hwnd);
+            let hmemdc = CreateCompatibleDC(hdc);
+            let mut bmi = BITMAPINFO::default();
+            bmi.bmiHeader.biSize = size_of::<BITMAPINFOHEADER>() as u32;
+            bmi.bmiHeader.biWidth = width as i32;
+            bmi.bmiHeader.biHeight = height as i32;
+            bmi.bmiHeader.biPlanes = 1;
+            bmi.bmiHeader.biBitCount = 32;
+            bmi.bmiHeader.biCompression = 0; // BI_RGB
+            let mut bits: *mut c_void = null_mut();
+            let hbm = CreateDIBSection(hdc, &bmi, DIB_RGB_COLORS, &mut bits, None, 0)?;
+            let hbm_old = SelectObject(hmemdc, hbm);
+            self.hwnd = hwnd;
+            self.hdc = hdc;
+            self.hmemdc = hmemdc;
+            self.hbm = hbm;
+            self.hbm_old = hbm_old;
+            self.bits = bits as *mut u8;
+            self.width = width;
+            self.height = height;
+        }
+        Ok(())
+    }
+    pub fn paint(&mut self, image: &Image, x: i32, y: i32) -> anyhow::Result<()> {
+        unsafe {
+            self.ensure_surface(image.width, image.height)?;
+            std::ptr::copy_nonoverlapping(image.buf.as_ptr(), self.bits, image.buf.len());
+            BitBlt(self.hdc, x, y, self.width as i32, self.height as i32, self.hmemdc, 0, 0, SRCCOPY);
+            Ok(())
+        }
+    }
+    /// Composites `image` over whatever the destination currently shows, via `AlphaBlend` with a
+    /// constant `alpha` factor, instead of clobbering it the way `paint` does.
+    pub fn paint_blended(&mut self, image: &Image, x: i32, y: i32, alpha: u8) -> anyhow::Result<()> {
+        unsafe {
+            self.ensure_surface(image.width, image.height)?;
+            std::ptr::copy_nonoverlapping(image.buf.as_ptr(), self.bits, image.buf.len());
+            AlphaBlend(self.hdc, x, y, self.width as i32, self.height as i32, self.hmemdc, 0, 0, self.width as i32, self.height as i32, constant_alpha_blend_function(alpha));
+            Ok(())
+        }
+    }
+    /// Like `paint`, but pixels where `is_transparent` returns `true` are left untouched in the
+    /// destination instead of being overwritten, via a 1-bpp `MaskBlt` mask.
+    pub fn paint_masked<F: Fn(&Image, usize, usize) -> bool>(&mut self, image: &Image, x: i32, y: i32, is_transparent: F) -> anyhow::Result<()> {
+        unsafe {
+            self.ensure_surface(image.width, image.height)?;
+            std::ptr::copy_nonoverlapping(image.buf.as_ptr(), self.bits, image.buf.len());
+            let (_, mask_buf) = build_transparency_mask(self.width, self.height, |xx, yy| is_transparent(image, xx, yy));
+            let hmask = CreateBitmap(self.width as i32, self.height as i32, 1, 1, Some(mask_buf.as_ptr() as _));
+            // MAKEROP4(fore, back): SRCCOPY where the mask bit is 1, the "leave destination alone" ROP where it's 0.
+            let rop4 = ((0xAA0000u32 << 8) & 0xFF000000) | SRCCOPY.0 as u32;
+            MaskBlt(self.hdc, x, y, self.width as i32, self.height as i32, self.hmemdc, 0, 0, hmask, 0, 0, rop4);
+            DeleteObject(hmask);
+            Ok(())
+        }
+    }
+}
+
+/// `BLENDFUNCTION` for `AlphaBlend`-ing a fully opaque BGRA source at a constant `alpha`, pulled out
+/// of `paint_blended` so the field values are unit-testable without a live window.
+fn constant_alpha_blend_function(alpha: u8) -> BLENDFUNCTION {
+    BLENDFUNCTION {
+        BlendOp: AC_SRC_OVER as u8,
+        BlendFlags: 0,
+        SourceConstantAlpha: alpha,
+        AlphaFormat: 0,
+    }
+}
+
+/// Builds the 1-bpp mask bitmap for `paint_masked`, row-padded to a 2-byte (word) boundary as
+/// required by `CreateBitmap`, pulled out so the bit layout and polarity are unit-testable without
+/// a live window. A set bit means "paint this pixel" (`MaskBlt`'s foreground ROP applies there);
+/// `is_transparent` pixels are left clear so the destination shows through.
+fn build_transparency_mask<F: Fn(usize, usize) -> bool>(width: usize, height: usize, is_transparent: F) -> (usize, Vec<u8>) {
+    let mask_stride = ((width + 15) / 16) * 2;
+    let mut mask_buf = vec![0u8; mask_stride * height];
+    for yy in 0..height {
+        for xx in 0..width {
+            if !is_transparent(xx, yy) {
+                mask_buf[yy * mask_stride + xx / 8] |= 0x80 >> (xx % 8);
+            }
+        }
+    }
+    (mask_stride, mask_buf)
+}
+
+impl Drop for WindowPainter {
+    fn drop(&mut self) {
+        unsafe { self.teardown() }
+    }
+}
+
+// ffplay is left to decide its own window size; the captured frame is resized to this width
+// (preserving aspect ratio) by `Image::resize` instead of forcing ffplay's output via `-x`.
+const CAPTURE_TARGET_WIDTH: usize = 540;
+
 fn main() -> anyhow::Result<()> {
     unsafe {
         let _ = SetProcessDpiAwareness(PROCESS_PER_MONITOR_DPI_AWARE);
@@ -152,26 +559,45 @@ fn main() -> anyhow::Result<()> {
             Command::new("taskmgr.exe")
                 .spawn()?;
         }
-        let hwnd_ffplay = FindWindowW("SDL_app", "ffplay");
-        if hwnd_ffplay.0 == 0 {
-            Command::new("ffplay.exe")
-                .arg("-x")
-                .arg("540")
-                .arg("-volume")
-                .arg("1")
-                .arg("-window_title")
-                .arg("ffplay")
-                .arg(std::env::args().skip(1).next().unwrap())
-                .spawn()?;
+        let input_path = std::env::args().skip(1).next().unwrap();
+        // A .bmp input is a static picture read directly (no ffplay/window capture needed); anything
+        // else is handed to ffplay as before and captured off its window each frame.
+        let is_static_bmp = input_path.to_ascii_lowercase().ends_with(".bmp");
+        if !is_static_bmp {
+            let hwnd_ffplay = FindWindowW("SDL_app", "ffplay");
+            if hwnd_ffplay.0 == 0 {
+                Command::new("ffplay.exe")
+                    .arg("-volume")
+                    .arg("1")
+                    .arg("-window_title")
+                    .arg("ffplay")
+                    .arg(&input_path)
+                    .spawn()?;
+            }
         }
 
+        let mut capture = WindowCapture::new("SDL_app", "ffplay");
+        let mut painter = WindowPainter::new("TaskManagerWindow", "任务管理器");
         loop {
-            if let Ok(img) = Image::from_window("SDL_app", "ffplay", 0, 0) {
+            let captured = if is_static_bmp {
+                Image::from_bmp_file(&input_path)
+            } else {
+                capture.capture(0, 0)
+            };
+            if let Ok(mut img) = captured {
                 let hwnd_taskmgr = FindWindowW("TaskManagerWindow", "任务管理器");
                 if hwnd_taskmgr.0 != 0 {
-                    let img2 = img.to_taskmgr_style();
+                    if img.width != CAPTURE_TARGET_WIDTH {
+                        // Clamp to 1: a thin/letterboxed capture can otherwise round target_height down to 0,
+                        // which resize happily returns as a real 0-height Image and to_taskmgr_style_dithered
+                        // then panics on (height - 1) underflow.
+                        let target_height = (img.height * CAPTURE_TARGET_WIDTH / img.width).max(1);
+                        img = img.resize(CAPTURE_TARGET_WIDTH, target_height);
+                    }
+                    img.apply_otsu_threshold();
+                    let img2 = img.to_taskmgr_style_dithered();
                     SetWindowPos(hwnd_taskmgr, None, 0, 0, (img2.width + 396) as i32, (img2.height + 508) as i32, SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE);
-                    let _ = img2.paint_to_window("TaskManagerWindow", "任务管理器", 350, 126);
+                    let _ = painter.paint(&img2, 350, 126);
                 } else {
                     sleep(Duration::from_millis(16));
                 }
@@ -180,4 +606,90 @@ fn main() -> anyhow::Result<()> {
             }
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resize_sets_requested_output_dimensions() {
+        let img = Image::from_fn(4, 4, |x, y| ((x * 60) as u8, (y * 60) as u8, 0));
+        let resized = img.resize(8, 2);
+        assert_eq!(resized.width, 8);
+        assert_eq!(resized.height, 2);
+    }
+
+    #[test]
+    fn resize_zero_size_source_returns_blank_target_instead_of_panicking() {
+        let img = Image::new(0, 5);
+        let resized = img.resize(10, 10);
+        assert_eq!(resized.width, 10);
+        assert_eq!(resized.height, 10);
+    }
+
+    #[test]
+    fn to_taskmgr_style_dithered_matches_plain_style_dimensions() {
+        let img = Image::from_fn(10, 10, |x, y| if (x + y) % 2 == 0 { (255, 255, 255) } else { (0, 0, 0) });
+        let styled = img.to_taskmgr_style_dithered();
+        assert_eq!(styled.width, img.width - 1);
+        assert_eq!(styled.height, img.height - 1);
+    }
+
+    #[test]
+    fn bmp_round_trip_preserves_pixels() -> anyhow::Result<()> {
+        let img = Image::from_fn(6, 4, |x, y| ((x * 10) as u8, (y * 10) as u8, 128));
+        let path = std::env::temp_dir().join(format!("taskmgr_drawing_test_{}.bmp", std::process::id()));
+        img.to_bmp_file(&path)?;
+        let loaded = Image::from_bmp_file(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(loaded.width, img.width);
+        assert_eq!(loaded.height, img.height);
+        for y in 0..img.height {
+            for x in 0..img.width {
+                assert_eq!(loaded.get_color(x, y), img.get_color(x, y));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn from_bmp_file_rejects_truncated_pixel_data() -> anyhow::Result<()> {
+        let img = Image::from_fn(6, 4, |_, _| (1, 2, 3));
+        let path = std::env::temp_dir().join(format!("taskmgr_drawing_test_truncated_{}.bmp", std::process::id()));
+        img.to_bmp_file(&path)?;
+        let mut data = std::fs::read(&path)?;
+        data.truncate(data.len() - 5);
+        std::fs::write(&path, &data)?;
+        let result = Image::from_bmp_file(&path);
+        std::fs::remove_file(&path)?;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn compute_otsu_threshold_splits_bimodal_image() {
+        let img = Image::from_fn(20, 20, |x, _| if x < 10 { (10, 10, 10) } else { (240, 240, 240) });
+        let threshold = img.compute_otsu_threshold();
+        // The two greys are 9 and 240 (r/4 + g/2 + b/4); is_white is gray > threshold, so 9 <= t < 240 splits them.
+        assert!(threshold >= 9 && threshold < 240);
+    }
+
+    #[test]
+    fn constant_alpha_blend_function_carries_the_requested_alpha() {
+        let blend_function = constant_alpha_blend_function(128);
+        assert_eq!(blend_function.BlendOp, AC_SRC_OVER as u8);
+        assert_eq!(blend_function.BlendFlags, 0);
+        assert_eq!(blend_function.SourceConstantAlpha, 128);
+        assert_eq!(blend_function.AlphaFormat, 0);
+    }
+
+    #[test]
+    fn build_transparency_mask_sets_bits_for_opaque_pixels_only() {
+        let (stride, mask) = build_transparency_mask(10, 2, |x, _| x >= 5);
+        assert_eq!(stride, 2); // (10 + 15) / 16 * 2
+        // Row 0: pixels 0..5 are opaque (bit set), 5..10 are transparent (bit clear).
+        assert_eq!(mask[0], 0b1111_1000);
+        assert_eq!(mask[1], 0b0000_0000);
+    }
 }
\ No newline at end of file